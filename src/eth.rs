@@ -0,0 +1,384 @@
+//! Support for broadcasting a signed Safe transaction directly to an
+//! Ethereum JSON-RPC node, bypassing the Safe relay service entirely. This
+//! is useful when the relay is unreachable or simply not deployed on a
+//! given network.
+
+use crate::{
+    address::Address,
+    hash,
+    rlp::{self, Item},
+    safe::{
+        abi,
+        data::{Estimate, SafeInfo, SignedSafeTransaction},
+        http,
+    },
+    secret::PrivateKey,
+};
+use anyhow::{anyhow, bail, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// A client for an Ethereum JSON-RPC endpoint.
+pub struct Provider {
+    url: String,
+}
+
+impl Provider {
+    /// Creates a new provider for the node at the specified URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        Provider { url: url.into() }
+    }
+
+    /// Returns the next account nonce to use for a transaction from the
+    /// specified address.
+    pub fn transaction_count(&self, address: Address) -> Result<u64> {
+        let count: String = self.call(
+            "eth_getTransactionCount",
+            json!([address.to_string(), "pending"]),
+        )?;
+        parse_u64(&count)
+    }
+
+    /// Returns the current legacy gas price.
+    pub fn gas_price(&self) -> Result<u128> {
+        let price: String = self.call("eth_gasPrice", json!([]))?;
+        parse_u128(&price)
+    }
+
+    /// Returns a `(max_priority_fee_per_gas, max_fee_per_gas)` suggestion for
+    /// an EIP-1559 transaction, derived from the most recent block's base
+    /// fee and reward history.
+    pub fn fee_history(&self) -> Result<(u128, u128)> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct FeeHistory {
+            base_fee_per_gas: Vec<String>,
+            reward: Vec<Vec<String>>,
+        }
+
+        let history: FeeHistory = self.call(
+            "eth_feeHistory",
+            json!([1, "pending", [50]]),
+        )?;
+
+        let base_fee = history
+            .base_fee_per_gas
+            .last()
+            .ok_or_else(|| anyhow!("missing base fee in fee history"))?;
+        let base_fee = parse_u128(base_fee)?;
+
+        let priority_fee = history
+            .reward
+            .last()
+            .and_then(|rewards| rewards.first())
+            .map(|reward| parse_u128(reward))
+            .transpose()?
+            .unwrap_or(0);
+
+        let max_fee = base_fee.saturating_mul(2).saturating_add(priority_fee);
+        Ok((priority_fee, max_fee))
+    }
+
+    /// Calls a contract method with no value transfer, returning its raw
+    /// return data. This performs an `eth_call` against the latest block.
+    pub fn eth_call(&self, to: Address, data: &[u8]) -> Result<Vec<u8>> {
+        let result: String = self.call(
+            "eth_call",
+            json!([
+                {
+                    "to": to.to_string(),
+                    "data": format!("0x{}", hex::encode(data)),
+                },
+                "latest",
+            ]),
+        )?;
+
+        let result = result.strip_prefix("0x").unwrap_or(&result);
+        Ok(hex::decode(result)?)
+    }
+
+    /// Broadcasts a raw signed transaction, returning its transaction hash.
+    pub fn send_raw_transaction(&self, raw: &[u8]) -> Result<[u8; 32]> {
+        let hash: String =
+            self.call("eth_sendRawTransaction", json!([format!("0x{}", hex::encode(raw))]))?;
+        let hash = hash.strip_prefix("0x").unwrap_or(&hash);
+        let hash = hex::decode(hash)?;
+        hash.try_into()
+            .map_err(|_| anyhow!("invalid transaction hash returned by node"))
+    }
+
+    fn call<T>(&self, method: &str, params: Value) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        #[derive(Serialize)]
+        struct Request<'a> {
+            jsonrpc: &'a str,
+            method: &'a str,
+            params: Value,
+            id: u64,
+        }
+        #[derive(Deserialize)]
+        struct RpcError {
+            code: i64,
+            message: String,
+        }
+        #[derive(Deserialize)]
+        struct Response<T> {
+            result: Option<T>,
+            error: Option<RpcError>,
+        }
+
+        let request = Request {
+            jsonrpc: "2.0",
+            method,
+            params,
+            id: 1,
+        };
+        let response: Response<T> = http::post_json(&self.url, &request)?;
+
+        match response.result {
+            Some(result) => Ok(result),
+            None => match response.error {
+                Some(error) => bail!("JSON-RPC error {}: {}", error.code, error.message),
+                None => bail!("JSON-RPC response missing both result and error"),
+            },
+        }
+    }
+}
+
+fn parse_u64(value: &str) -> Result<u64> {
+    u64::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map_err(|err| anyhow!("invalid hex integer '{}': {}", value, err))
+}
+
+fn parse_u128(value: &str) -> Result<u128> {
+    u128::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map_err(|err| anyhow!("invalid hex integer '{}': {}", value, err))
+}
+
+/// A raw Ethereum transaction ready to be signed.
+pub enum Transaction {
+    /// A legacy (pre-EIP-1559) transaction.
+    Legacy {
+        nonce: u64,
+        gas_price: u128,
+        gas_limit: u128,
+        to: Address,
+        value: u128,
+        data: Vec<u8>,
+        chain_id: u64,
+    },
+    /// An EIP-1559 typed transaction.
+    Eip1559 {
+        nonce: u64,
+        max_priority_fee_per_gas: u128,
+        max_fee_per_gas: u128,
+        gas_limit: u128,
+        to: Address,
+        value: u128,
+        data: Vec<u8>,
+        chain_id: u64,
+    },
+}
+
+impl Transaction {
+    /// Computes the hash that must be signed to authorize this transaction.
+    fn signing_hash(&self) -> [u8; 32] {
+        match self {
+            Transaction::Legacy {
+                nonce,
+                gas_price,
+                gas_limit,
+                to,
+                value,
+                data,
+                chain_id,
+            } => hash::keccak256(rlp::encode(&Item::list(vec![
+                Item::uint(*nonce as _),
+                Item::uint(*gas_price),
+                Item::uint(*gas_limit),
+                Item::bytes(&**to as &[u8]),
+                Item::uint(*value),
+                Item::bytes(data.clone()),
+                Item::uint(*chain_id as _),
+                Item::uint(0),
+                Item::uint(0),
+            ]))),
+            Transaction::Eip1559 {
+                nonce,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                gas_limit,
+                to,
+                value,
+                data,
+                chain_id,
+            } => {
+                let payload = rlp::encode(&Item::list(vec![
+                    Item::uint(*chain_id as _),
+                    Item::uint(*nonce as _),
+                    Item::uint(*max_priority_fee_per_gas),
+                    Item::uint(*max_fee_per_gas),
+                    Item::uint(*gas_limit),
+                    Item::bytes(&**to as &[u8]),
+                    Item::uint(*value),
+                    Item::bytes(data.clone()),
+                    Item::list(vec![]),
+                ]));
+                let mut buffer = vec![0x02];
+                buffer.extend(payload);
+                hash::keccak256(buffer)
+            }
+        }
+    }
+
+    /// Signs the transaction with the specified private key and RLP-encodes
+    /// the result, ready to be broadcast with `eth_sendRawTransaction`.
+    fn sign_and_encode(&self, key: &PrivateKey) -> Vec<u8> {
+        let signature = key.sign(self.signing_hash());
+        let recovery_id = signature.v - 27;
+
+        match self {
+            Transaction::Legacy {
+                nonce,
+                gas_price,
+                gas_limit,
+                to,
+                value,
+                data,
+                chain_id,
+            } => {
+                let v = recovery_id as u64 + chain_id * 2 + 35;
+                rlp::encode(&Item::list(vec![
+                    Item::uint(*nonce as _),
+                    Item::uint(*gas_price),
+                    Item::uint(*gas_limit),
+                    Item::bytes(&**to as &[u8]),
+                    Item::uint(*value),
+                    Item::bytes(data.clone()),
+                    Item::uint(v as _),
+                    Item::uint_bytes(&signature.r),
+                    Item::uint_bytes(&signature.s),
+                ]))
+            }
+            Transaction::Eip1559 {
+                nonce,
+                max_priority_fee_per_gas,
+                max_fee_per_gas,
+                gas_limit,
+                to,
+                value,
+                data,
+                chain_id,
+            } => {
+                let payload = rlp::encode(&Item::list(vec![
+                    Item::uint(*chain_id as _),
+                    Item::uint(*nonce as _),
+                    Item::uint(*max_priority_fee_per_gas),
+                    Item::uint(*max_fee_per_gas),
+                    Item::uint(*gas_limit),
+                    Item::bytes(&**to as &[u8]),
+                    Item::uint(*value),
+                    Item::bytes(data.clone()),
+                    Item::list(vec![]),
+                    Item::uint(recovery_id as _),
+                    Item::uint_bytes(&signature.r),
+                    Item::uint_bytes(&signature.s),
+                ]));
+                let mut buffer = vec![0x02];
+                buffer.extend(payload);
+                buffer
+            }
+        }
+    }
+}
+
+/// Retrieves a Safe's on-chain configuration directly through `eth_call`,
+/// reconstructing the same `SafeInfo` the relay service would otherwise
+/// provide. This lets the tool operate against any network or self-hosted
+/// node where the relay isn't running.
+pub fn get_safe_info(provider: &Provider, safe: Address) -> Result<SafeInfo> {
+    let nonce = abi::decode_uint(&provider.eth_call(safe, &abi::nonce())?)? as u64;
+    let threshold = abi::decode_uint(&provider.eth_call(safe, &abi::get_threshold())?)? as usize;
+    let owners = abi::decode_address_array(&provider.eth_call(safe, &abi::get_owners())?)?;
+    let version = abi::decode_string(&provider.eth_call(safe, &abi::version())?)?;
+
+    Ok(SafeInfo {
+        nonce,
+        threshold,
+        owners,
+        version,
+    })
+}
+
+/// Produces a fixed Safe transaction gas estimate, for use when no relay is
+/// available. Unlike the relay's `/estimate` endpoint, this doesn't
+/// simulate the transaction against the Safe contract, so it deliberately
+/// uses a generous `safe_tx_gas` in exchange for not depending on the relay,
+/// the same trade-off `execute` makes for the outer transaction's gas
+/// limit.
+///
+/// `gas_price` and `base_gas` are fixed at zero, since this Safe
+/// transaction will be self-executed rather than relayed: a non-zero
+/// `gas_price` would make `execTransaction` pay a `(gasUsed + baseGas) *
+/// gasPrice` ETH refund out of the Safe's own balance to the refund
+/// receiver, which isn't wanted here. The outer Ethereum transaction's gas
+/// price is fetched separately by `execute`.
+pub fn estimate_safe_transaction(gas_token: Option<Address>) -> Result<Estimate> {
+    Ok(Estimate {
+        safe_tx_gas: 250_000,
+        base_gas: 0,
+        gas_price: 0,
+        last_used_nonce: None,
+        gas_token,
+        refund_receiver: None,
+    })
+}
+
+/// Builds and broadcasts a raw Ethereum transaction that calls
+/// `execTransaction` on the Safe with the already-signed Safe transaction,
+/// using the recovery key to pay for and authorize the outer transaction.
+pub fn execute(
+    provider: &Provider,
+    safe: Address,
+    tx: &SignedSafeTransaction,
+    key: &PrivateKey,
+    chain_id: u64,
+    eip1559: bool,
+) -> Result<[u8; 32]> {
+    let nonce = provider.transaction_count(key.address())?;
+    let data = abi::exec_transaction(tx);
+    // No explicit gas limit estimate is available off of the relay in this
+    // path; fall back to a generous limit: the Safe transaction's own gas
+    // (at least 250,000) plus 100,000 for the surrounding execution.
+    let gas_limit = 250_000u128.max(tx.safe_tx_gas + tx.data_gas) + 100_000;
+
+    let transaction = if eip1559 {
+        let (max_priority_fee_per_gas, max_fee_per_gas) = provider.fee_history()?;
+        Transaction::Eip1559 {
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            to: safe,
+            value: 0,
+            data,
+            chain_id,
+        }
+    } else {
+        let gas_price = provider.gas_price()?;
+        Transaction::Legacy {
+            nonce,
+            gas_price,
+            gas_limit,
+            to: safe,
+            value: 0,
+            data,
+            chain_id,
+        }
+    };
+
+    let raw = transaction.sign_and_encode(key);
+    provider.send_raw_transaction(&raw)
+}
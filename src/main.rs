@@ -1,6 +1,8 @@
 mod address;
+mod eth;
 mod etherscan;
 mod hash;
+mod rlp;
 mod safe;
 mod secret;
 mod term;
@@ -10,7 +12,7 @@ use crate::{
     safe::{
         abi,
         data::{EstimateParameters, Operation},
-        tx::SafeTransaction,
+        tx::{self, SafeTransaction},
         Client, Network,
     },
     secret::PrivateKey,
@@ -40,6 +42,19 @@ struct Options {
     /// The token to pay transaction gas in.
     #[structopt(long = "gas-token")]
     gas_token: Option<Address>,
+
+    /// An Ethereum JSON-RPC endpoint to use instead of the Safe relay
+    /// service: the Safe's configuration is read directly from the
+    /// contract, and the migration transaction is broadcast as a raw
+    /// transaction. Useful when the relay is down or not deployed on a
+    /// network.
+    #[structopt(long = "rpc-url")]
+    rpc_url: Option<String>,
+
+    /// Broadcast the migration transaction as an EIP-1559 transaction
+    /// instead of a legacy one. Only applies when `--rpc-url` is used.
+    #[structopt(long)]
+    eip1559: bool,
 }
 
 fn main() {
@@ -65,12 +80,22 @@ fn run(options: Options) -> Result<()> {
     println!("  - {}", recovery_key.address());
     println!("  - {}", secondary_recovery_address);
 
-    let network = options.network.unwrap_or(Network::Rinkeby);
+    let network = match options.network {
+        Some(network) => network,
+        None => "rinkeby".parse().expect("rinkeby is a built-in network"),
+    };
     let client = Client::for_network(network);
+    let provider = options.rpc_url.map(eth::Provider::new);
 
-    let info = client.get_safe(options.safe)?;
+    let info = match &provider {
+        Some(provider) => eth::get_safe_info(provider, options.safe)?,
+        None => client.get_safe(options.safe)?,
+    };
     {
-        ensure!(info.version == "1.1.1", "unsupported Safe version");
+        ensure!(
+            tx::is_supported_version(&info.version),
+            "unsupported Safe version"
+        );
         ensure!(
             info.owners.len() == 3 && info.threshold == 1,
             "unsupported Safe configuration"
@@ -82,14 +107,17 @@ fn run(options: Options) -> Result<()> {
         );
     }
 
-    let estimate = client.estimate_safe_transaction(EstimateParameters {
-        safe: options.safe,
-        to: options.safe,
-        value: 0,
-        data: abi::add_owner_with_threshold(options.owner, 1),
-        operation: Operation::Call,
-        gas_token: options.gas_token,
-    })?;
+    let estimate = match &provider {
+        Some(_) => eth::estimate_safe_transaction(options.gas_token)?,
+        None => client.estimate_safe_transaction(EstimateParameters {
+            safe: options.safe,
+            to: options.safe,
+            value: 0,
+            data: abi::add_owner_with_threshold(options.owner, 1),
+            operation: Operation::Call,
+            gas_token: options.gas_token,
+        })?,
+    };
 
     term::confirm(format!(
         "About to add {} as an owner (yes to continue)",
@@ -123,16 +151,37 @@ fn run(options: Options) -> Result<()> {
     println!("  gas token: {}", display_option(tx.gas_token));
     println!("  refund receiver: {}", display_option(tx.refund_receiver));
     println!("  nonce: {}", tx.nonce);
-    println!("  hash: 0x{}", hex::encode(tx.hash(options.safe)));
+    let tx_hash = tx.hash(options.safe, network.chain_id, &info.version);
+    println!("  hash: 0x{}", hex::encode(tx_hash));
     term::confirm("Are you still 100% sure")?;
 
-    let signed_tx = tx.sign(options.safe, &recovery_key);
+    let signed_tx = tx.sign(options.safe, network.chain_id, &info.version, &recovery_key);
     println!("Using signature {}", signed_tx.signatures[0]);
+    ensure!(
+        signed_tx.signatures[0].recover(tx_hash)? == recovery_key.address(),
+        "recovered signer does not match the recovery key"
+    );
     term::confirm("Are absolutely positively undoubtedly sure")?;
 
-    let executed_tx = client.post_transaction(signed_tx)?;
-    println!("Transaction successfully relayed:");
-    println!("{}", etherscan::render_link(network, &executed_tx));
+    match &provider {
+        Some(provider) => {
+            let transaction_hash = eth::execute(
+                provider,
+                options.safe,
+                &signed_tx,
+                &recovery_key,
+                network.chain_id,
+                options.eip1559,
+            )?;
+            println!("Transaction successfully broadcast:");
+            println!("0x{}", hex::encode(transaction_hash));
+        }
+        None => {
+            let executed_tx = client.post_transaction(signed_tx)?;
+            println!("Transaction successfully relayed:");
+            println!("{}", etherscan::render_link(network, &executed_tx));
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,140 @@
+//! A minimal RLP (Recursive Length Prefix) encoder, just enough to build
+//! the raw Ethereum transactions used by the [`crate::eth`] module.
+
+/// An RLP-encodable item.
+pub enum Item {
+    /// A byte string.
+    Bytes(Vec<u8>),
+    /// A list of items.
+    List(Vec<Item>),
+}
+
+impl Item {
+    /// Creates a new RLP byte string item.
+    pub fn bytes(data: impl Into<Vec<u8>>) -> Self {
+        Item::Bytes(data.into())
+    }
+
+    /// Creates a new RLP byte string item from an unsigned integer, encoded
+    /// as its minimal big-endian representation (with no leading zero
+    /// bytes, and the empty string for `0`).
+    pub fn uint(value: u128) -> Self {
+        let bytes = value.to_be_bytes();
+        Self::uint_bytes(&bytes)
+    }
+
+    /// Creates a new RLP byte string item from a big-endian unsigned
+    /// integer of arbitrary width (such as a 256-bit signature `r`/`s`
+    /// value), stripped of leading zero bytes the same way as `uint`. RLP
+    /// integers must be minimally encoded, so a fixed-width buffer can't be
+    /// passed to `bytes` directly without risking a non-canonical encoding.
+    pub fn uint_bytes(value: &[u8]) -> Self {
+        let start = value.iter().position(|&byte| byte != 0).unwrap_or(value.len());
+        Item::Bytes(value[start..].to_vec())
+    }
+
+    /// Creates a new RLP list item.
+    pub fn list(items: impl Into<Vec<Item>>) -> Self {
+        Item::List(items.into())
+    }
+}
+
+/// RLP-encodes the specified item.
+pub fn encode(item: &Item) -> Vec<u8> {
+    match item {
+        Item::Bytes(data) => encode_bytes(data),
+        Item::List(items) => {
+            let payload: Vec<u8> = items.iter().flat_map(encode).collect();
+            let mut result = encode_header(0xc0, payload.len());
+            result.extend(payload);
+            result
+        }
+    }
+}
+
+fn encode_bytes(data: &[u8]) -> Vec<u8> {
+    if data.len() == 1 && data[0] < 0x80 {
+        return vec![data[0]];
+    }
+
+    let mut result = encode_header(0x80, data.len());
+    result.extend_from_slice(data);
+    result
+}
+
+fn encode_header(offset: u8, len: usize) -> Vec<u8> {
+    if len <= 55 {
+        vec![offset + len as u8]
+    } else {
+        let len_bytes = Item::uint(len as u128);
+        let len_bytes = match len_bytes {
+            Item::Bytes(bytes) => bytes,
+            Item::List(_) => unreachable!(),
+        };
+        let mut result = vec![offset + 55 + len_bytes.len() as u8];
+        result.extend(len_bytes);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn encodes_empty_string() {
+        assert_eq!(encode(&Item::bytes(vec![])), vec![0x80]);
+    }
+
+    #[test]
+    fn encodes_single_byte() {
+        assert_eq!(encode(&Item::bytes(vec![0x7f])), vec![0x7f]);
+        assert_eq!(encode(&Item::bytes(vec![0x80])), vec![0x81, 0x80]);
+    }
+
+    #[test]
+    fn encodes_short_string() {
+        assert_eq!(
+            encode(&Item::bytes(*b"dog")),
+            hex!("83646f67"),
+        );
+    }
+
+    #[test]
+    fn encodes_long_string() {
+        let data = vec![b'a'; 56];
+        let encoded = encode(&Item::bytes(data.clone()));
+        assert_eq!(encoded[0], 0xb8);
+        assert_eq!(encoded[1], 56);
+        assert_eq!(&encoded[2..], &data[..]);
+    }
+
+    #[test]
+    fn encodes_uint() {
+        assert_eq!(encode(&Item::uint(0)), vec![0x80]);
+        assert_eq!(encode(&Item::uint(0x0400)), hex!("820400"));
+    }
+
+    #[test]
+    fn encodes_uint_bytes_without_leading_zeros() {
+        assert_eq!(encode(&Item::uint_bytes(&[0; 32])), vec![0x80]);
+        assert_eq!(
+            encode(&Item::uint_bytes(&hex!(
+                "0000000000000000000000000000000000000000000000000000000000000400"
+            ))),
+            hex!("820400"),
+        );
+    }
+
+    #[test]
+    fn encodes_list() {
+        assert_eq!(
+            encode(&Item::list(vec![
+                Item::bytes(*b"cat"),
+                Item::bytes(*b"dog"),
+            ])),
+            hex!("c88363617483646f67"),
+        );
+    }
+}
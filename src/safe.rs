@@ -1,51 +1,99 @@
 pub mod abi;
 pub mod data;
-mod http;
+pub(crate) mod http;
 pub mod tx;
 
-use self::data::*;
+use self::{data::*, tx::SignedSafeTransactionDump};
 use crate::address::Address;
-use anyhow::{bail, Result};
+use anyhow::{anyhow, Result};
 use std::str::FromStr;
 
-/// Networks supporting Safe services.
+/// A network with known Safe service deployments, identified by its EIP-155
+/// chain ID.
+///
+/// This is a plain data table rather than a closed enum, so that supporting
+/// a new chain where Safe is deployed is a matter of adding a row instead of
+/// patching the crate.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-#[repr(u64)]
-pub enum Network {
-    Mainnet = 1,
-    Rinkeby = 4,
+pub struct Network {
+    /// The EIP-155 chain ID.
+    pub chain_id: u64,
+    /// A short, friendly name for the network (e.g. `"mainnet"`).
+    pub name: &'static str,
+    /// The host name of the block explorer for this network (e.g.
+    /// `"etherscan.io"`).
+    pub explorer: &'static str,
+    relay: &'static str,
+    tx_service: &'static str,
 }
 
+/// The registry of networks with known Safe service deployments.
+const NETWORKS: &[Network] = &[
+    Network {
+        chain_id: 1,
+        name: "mainnet",
+        explorer: "etherscan.io",
+        relay: "https://safe-relay.gnosis.io/api",
+        tx_service: "https://safe-transaction.gnosis.io/api",
+    },
+    Network {
+        chain_id: 4,
+        name: "rinkeby",
+        explorer: "rinkeby.etherscan.io",
+        relay: "https://safe-relay.rinkeby.gnosis.io/api",
+        tx_service: "https://safe-transaction.rinkeby.gnosis.io/api",
+    },
+];
+
 impl FromStr for Network {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        Ok(match s {
-            "mainnet" => Network::Mainnet,
-            "rinkeby" => Network::Rinkeby,
-            _ => bail!("invalid network '{}'", s),
-        })
+        NETWORKS
+            .iter()
+            .copied()
+            .find(|network| network.name == s)
+            .ok_or_else(|| anyhow!("invalid network '{}'", s))
     }
 }
 
 /// A client to the Gnosis Safe Multisig services.
 pub struct Client {
     relay: String,
+    tx_service: String,
 }
 
 impl Client {
     /// Create a new client for the specified network.
     pub fn for_network(network: Network) -> Self {
-        let relay = match network {
-            Network::Mainnet => "https://safe-relay.gnosis.io/api",
-            Network::Rinkeby => "https://safe-relay.rinkeby.gnosis.io/api",
-        };
+        Self::with_custom_endpoints(network.relay, network.tx_service)
+    }
+
+    /// Create a new client for the network with the specified EIP-155 chain
+    /// ID, looking up its Safe service endpoints in the built-in registry.
+    pub fn for_chain_id(chain_id: u64) -> Result<Self> {
+        let network = NETWORKS
+            .iter()
+            .find(|network| network.chain_id == chain_id)
+            .ok_or_else(|| anyhow!("no known Safe services for chain ID {}", chain_id))?;
 
+        Ok(Self::for_network(*network))
+    }
+
+    /// Create a new client pointing at self-hosted relay and transaction
+    /// service endpoints, for Safe deployments not in the built-in registry.
+    pub fn with_custom_endpoints(relay: impl Into<String>, tx_service: impl Into<String>) -> Self {
         Client {
             relay: relay.into(),
+            tx_service: tx_service.into(),
         }
     }
 
+    /// The base URL of the Safe transaction service this client talks to.
+    pub fn transaction_service(&self) -> &str {
+        &self.tx_service
+    }
+
     /// Retrieves the list of owners of the specified Safe.
     pub fn get_safe(&self, safe: Address) -> Result<SafeInfo> {
         http::get_json(format!("{}/v1/safes/{}/", self.relay, safe))
@@ -53,19 +101,39 @@ impl Client {
 
     /// Estimates the gas for a transaction.
     pub fn estimate_safe_transaction(&self, tx: EstimateParameters) -> Result<Estimate> {
-        http::post_json(
+        http::post_json_or::<_, _, RelayError>(
             format!("{}/v2/safes/{}/transactions/estimate/", self.relay, tx.safe),
             &tx,
         )
     }
 
+    /// Retrieves the Safe's token (and native ETH) balances.
+    pub fn get_safe_balances(
+        &self,
+        safe: Address,
+        filters: BalancesFilters,
+    ) -> Result<Vec<TokenBalance>> {
+        http::get_json(format!(
+            "{}/v1/safes/{}/balances/{}",
+            self.relay,
+            safe,
+            filters.query_string(),
+        ))
+    }
+
     /// Posts a signed transaction to the relay service for execution.
     pub fn post_transaction(&self, tx: SignedSafeTransaction) -> Result<()> {
-        let result: serde_json::Value = http::post_json(
+        let result: serde_json::Value = http::post_json_or::<_, _, RelayError>(
             format!("{}/v1/safes/{}/transactions/", self.relay, tx.safe),
             &tx,
         )?;
         println!("{}", result);
         Ok(())
     }
+
+    /// Submits a transaction that was signed offline via `tx::sign_offline`
+    /// to the relay service for execution.
+    pub fn post_dumped(&self, signed_dump: SignedSafeTransactionDump) -> Result<()> {
+        self.post_transaction(signed_dump.into_signed_transaction())
+    }
 }
@@ -1,4 +1,6 @@
+use super::data::{Signature, SignedSafeTransaction};
 use crate::{address::Address, hash};
+use anyhow::{ensure, Result};
 
 /// ABI-encode transaction data to add a new owner.
 pub fn add_owner_with_threshold(owner: Address, threshold: u32) -> Vec<u8> {
@@ -11,6 +13,136 @@ pub fn add_owner_with_threshold(owner: Address, threshold: u32) -> Vec<u8> {
     data
 }
 
+/// ABI-encodes a call to the Safe's zero-argument `nonce()` getter.
+pub fn nonce() -> Vec<u8> {
+    selector("nonce()")
+}
+
+/// ABI-encodes a call to the Safe's zero-argument `getThreshold()` getter.
+pub fn get_threshold() -> Vec<u8> {
+    selector("getThreshold()")
+}
+
+/// ABI-encodes a call to the Safe's zero-argument `getOwners()` getter.
+pub fn get_owners() -> Vec<u8> {
+    selector("getOwners()")
+}
+
+/// ABI-encodes a call to the Safe's zero-argument `VERSION()` getter.
+pub fn version() -> Vec<u8> {
+    selector("VERSION()")
+}
+
+fn selector(signature: &str) -> Vec<u8> {
+    hash::keccak256(signature)[..4].to_vec()
+}
+
+/// Decodes a single `uint256` return value.
+pub fn decode_uint(data: &[u8]) -> Result<u128> {
+    ensure!(data.len() >= 32, "return data too short for a uint256");
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&data[16..32]);
+    Ok(u128::from_be_bytes(bytes))
+}
+
+/// Decodes a dynamic `address[]` return value.
+pub fn decode_address_array(data: &[u8]) -> Result<Vec<Address>> {
+    let offset = decode_uint(data)? as usize;
+    let len = decode_uint(&data[offset..])? as usize;
+
+    let mut owners = Vec::with_capacity(len);
+    for i in 0..len {
+        let start = offset + 32 + i * 32;
+        ensure!(data.len() >= start + 32, "return data too short for address[]");
+        owners.push(Address::from_slice(&data[start + 12..start + 32]));
+    }
+    Ok(owners)
+}
+
+/// Decodes a dynamic `string` return value.
+pub fn decode_string(data: &[u8]) -> Result<String> {
+    let offset = decode_uint(data)? as usize;
+    let len = decode_uint(&data[offset..])? as usize;
+    let start = offset + 32;
+    ensure!(data.len() >= start + len, "return data too short for string");
+
+    Ok(String::from_utf8(data[start..start + len].to_vec())?)
+}
+
+/// ABI-encode a call to `execTransaction` on a Safe, allowing an
+/// already-signed Safe transaction to be executed directly against the Safe
+/// contract instead of being relayed.
+pub fn exec_transaction(tx: &SignedSafeTransaction) -> Vec<u8> {
+    let signatures = pack_signatures(&tx.signatures);
+
+    // Head: one 32-byte word per parameter; the two dynamic parameters
+    // (`data` and `signatures`) are encoded in the head as an offset into
+    // the tail, where their actual contents are written out.
+    let head_len = 10 * 32;
+    let data_offset = head_len;
+    let signatures_offset = data_offset + encoded_bytes_len(&tx.data);
+
+    let mut data = Vec::new();
+    data.extend_from_slice(
+        &hash::keccak256(
+            "execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)",
+        )[..4],
+    );
+
+    push_address(&mut data, tx.to);
+    push_uint(&mut data, tx.value);
+    push_uint(&mut data, data_offset as u128);
+    push_uint(&mut data, tx.operation as u128);
+    push_uint(&mut data, tx.safe_tx_gas);
+    push_uint(&mut data, tx.data_gas);
+    push_uint(&mut data, tx.gas_price);
+    push_address(&mut data, tx.gas_token.unwrap_or_default());
+    push_address(&mut data, tx.refund_receiver.unwrap_or_default());
+    push_uint(&mut data, signatures_offset as u128);
+
+    push_bytes(&mut data, &tx.data);
+    push_bytes(&mut data, &signatures);
+
+    data
+}
+
+/// Packs owner signatures into the flat `bytes` layout expected by
+/// `execTransaction`: each signature contributes 65 bytes (`r || s || v`).
+fn pack_signatures(signatures: &[Signature]) -> Vec<u8> {
+    let mut packed = Vec::with_capacity(signatures.len() * 65);
+    for signature in signatures {
+        packed.extend_from_slice(&signature.r);
+        packed.extend_from_slice(&signature.s);
+        packed.push(signature.v);
+    }
+    packed
+}
+
+fn push_address(data: &mut Vec<u8>, address: Address) {
+    data.extend_from_slice(&[0u8; 12]);
+    data.extend_from_slice(&*address);
+}
+
+fn push_uint(data: &mut Vec<u8>, value: u128) {
+    data.extend_from_slice(&[0u8; 16]);
+    data.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_bytes(data: &mut Vec<u8>, value: &[u8]) {
+    push_uint(data, value.len() as u128);
+    data.extend_from_slice(value);
+    data.extend(std::iter::repeat(0).take(padding_len(value.len())));
+}
+
+fn encoded_bytes_len(value: &[u8]) -> usize {
+    32 + value.len() + padding_len(value.len())
+}
+
+fn padding_len(len: usize) -> usize {
+    (32 - len % 32) % 32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -27,4 +159,89 @@ mod tests {
             ),
         )
     }
+
+    #[test]
+    fn encodes_exec_transaction() {
+        use super::super::data::Operation;
+
+        let tx = SignedSafeTransaction {
+            safe: Address([0; 20]),
+            to: Address([1; 20]),
+            value: 2,
+            data: vec![3],
+            operation: Operation::Call,
+            gas_token: Some(Address([7; 20])),
+            safe_tx_gas: 4,
+            data_gas: 5,
+            gas_price: 6,
+            refund_receiver: Some(Address([8; 20])),
+            nonce: 0,
+            signatures: vec![Signature {
+                v: 0x1b,
+                r: [0x09; 32],
+                s: [0x0a; 32],
+            }],
+        };
+
+        assert_eq!(
+            exec_transaction(&tx),
+            hex!(
+                "6a76120200000000000000000000000001010101010101010101010101010101
+                 0101010100000000000000000000000000000000000000000000000000000000
+                 0000000200000000000000000000000000000000000000000000000000000000
+                 0000014000000000000000000000000000000000000000000000000000000000
+                 0000000000000000000000000000000000000000000000000000000000000000
+                 0000000400000000000000000000000000000000000000000000000000000000
+                 0000000500000000000000000000000000000000000000000000000000000000
+                 0000000600000000000000000000000007070707070707070707070707070707
+                 0707070700000000000000000000000008080808080808080808080808080808
+                 0808080800000000000000000000000000000000000000000000000000000000
+                 0000018000000000000000000000000000000000000000000000000000000000
+                 0000000103000000000000000000000000000000000000000000000000000000
+                 0000000000000000000000000000000000000000000000000000000000000000
+                 0000004109090909090909090909090909090909090909090909090909090909
+                 090909090a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a
+                 0a0a0a0a1b000000000000000000000000000000000000000000000000000000
+                 00000000"
+            ),
+        )
+    }
+
+    #[test]
+    fn getter_selectors() {
+        assert_eq!(nonce(), hex!("affed0e0"));
+        assert_eq!(get_threshold(), hex!("e75235b8"));
+        assert_eq!(get_owners(), hex!("a0e67e2b"));
+        assert_eq!(version(), hex!("ffa1ad74"));
+    }
+
+    #[test]
+    fn decodes_uint() {
+        let data = hex!("0000000000000000000000000000000000000000000000000000000000000042");
+        assert_eq!(decode_uint(&data).unwrap(), 0x42);
+    }
+
+    #[test]
+    fn decodes_address_array() {
+        let data = hex!(
+            "0000000000000000000000000000000000000000000000000000000000000020
+             0000000000000000000000000000000000000000000000000000000000000002
+             0000000000000000000000000101010101010101010101010101010101010101
+             0000000000000000000000000202020202020202020202020202020202020202"
+        );
+        assert_eq!(
+            decode_address_array(&data).unwrap(),
+            vec![Address([1; 20]), Address([2; 20])],
+        );
+    }
+
+    #[test]
+    fn decodes_string() {
+        let data = hex!(
+            "0000000000000000000000000000000000000000000000000000000000000020
+             0000000000000000000000000000000000000000000000000000000000000005
+             312e332e30000000000000000000000000000000000000000000000000000000"
+        );
+        assert_eq!(decode_string(&data).unwrap(), "1.3.0");
+    }
 }
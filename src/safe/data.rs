@@ -1,8 +1,10 @@
 use crate::address::Address;
-use serde::{Deserialize, Serialize};
-use serde_repr::Serialize_repr;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use serde_with::{serde_as, skip_serializing_none};
 use std::fmt::{self, Display, Formatter};
+use thiserror::Error;
 
 /// Safe information returned by the relay service.
 #[derive(Debug, Deserialize)]
@@ -18,7 +20,7 @@ pub struct SafeInfo {
 }
 
 /// Safe operation kind.
-#[derive(Clone, Copy, Debug, Serialize_repr)]
+#[derive(Clone, Copy, Debug, Deserialize_repr, Serialize_repr)]
 #[repr(u8)]
 pub enum Operation {
     Call = 0,
@@ -103,8 +105,141 @@ pub struct SignedSafeTransaction {
     pub signatures: Vec<Signature>,
 }
 
+/// Query filters for the Safe balances endpoint.
+#[derive(Debug, Default)]
+pub struct BalancesFilters {
+    /// Only include tokens that are on the trusted token list.
+    pub trusted: Option<bool>,
+    /// Exclude tokens that have been flagged as spam.
+    pub exclude_spam: Option<bool>,
+}
+
+impl BalancesFilters {
+    /// Renders the filters as a URL querystring, including the leading `?`
+    /// when at least one filter is set.
+    pub fn query_string(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(trusted) = self.trusted {
+            params.push(format!("trusted={}", trusted));
+        }
+        if let Some(exclude_spam) = self.exclude_spam {
+            params.push(format!("exclude_spam={}", exclude_spam));
+        }
+
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", params.join("&"))
+        }
+    }
+}
+
+/// A Safe's balance of a single token, or of native ETH when `token_address`
+/// is `None`.
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBalance {
+    /// The token contract address, or `None` for the native asset.
+    pub token_address: Option<Address>,
+    /// Metadata about the token, or `None` for the native asset.
+    pub token: Option<TokenInfo>,
+    /// The raw token balance, in the token's smallest unit.
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub balance: u128,
+    /// The balance converted to fiat currency, if a conversion is available.
+    pub fiat_balance: Option<String>,
+    /// The fiat conversion rate used for `fiat_balance`, if available.
+    pub fiat_conversion: Option<String>,
+    /// When this balance was last updated.
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Metadata describing an ERC20 token.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    /// The token's name.
+    pub name: String,
+    /// The token's ticker symbol.
+    pub symbol: String,
+    /// The number of decimals used to display the token's balance.
+    pub decimals: u32,
+    /// A URL to the token's logo, if known.
+    pub logo_uri: Option<String>,
+}
+
+/// A structured error returned by the Safe relay service for a non-2xx
+/// response, so callers can match on the failure instead of string-scraping
+/// an opaque message.
+#[derive(Debug, Error)]
+pub enum RelayError {
+    /// Not enough owner signatures were provided for the Safe's threshold.
+    #[error("not enough owner signatures provided")]
+    InsufficientSignatures,
+    /// The transaction nonce has already been executed.
+    #[error("transaction nonce has already been used")]
+    NonceAlreadyUsed,
+    /// The relay was unable to estimate gas for the transaction.
+    #[error("gas estimation failed: {0}")]
+    EstimationFailed(String),
+    /// One of the provided signatures does not recover to a Safe owner.
+    #[error("invalid owner signature")]
+    InvalidSignature,
+    /// Any other relay error that doesn't map to one of the cases above.
+    #[error("relay error: {message}")]
+    Other {
+        /// The relay's error code, if one was given.
+        code: Option<u32>,
+        /// The relay's human-readable error message.
+        message: String,
+        /// Per-field validation details, if any were given.
+        arguments: Vec<String>,
+    },
+}
+
+/// The raw JSON error body returned by the Safe relay service.
+#[derive(Debug, Deserialize)]
+struct RelayErrorBody {
+    #[serde(default)]
+    code: Option<u32>,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    arguments: Vec<String>,
+}
+
+impl<'de> Deserialize<'de> for RelayError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let body = RelayErrorBody::deserialize(deserializer)?;
+        let message = body.message.unwrap_or_default();
+        let lower = message.to_lowercase();
+
+        // The relay doesn't expose stable machine-readable error codes for
+        // these cases, so classify by the (best-effort) message content.
+        Ok(if lower.contains("signatures") && lower.contains("threshold") {
+            RelayError::InsufficientSignatures
+        } else if lower.contains("nonce") {
+            RelayError::NonceAlreadyUsed
+        } else if lower.contains("estimat") {
+            RelayError::EstimationFailed(message)
+        } else if lower.contains("signature") {
+            RelayError::InvalidSignature
+        } else {
+            RelayError::Other {
+                code: body.code,
+                message,
+                arguments: body.arguments,
+            }
+        })
+    }
+}
+
 /// A safe signature.
-#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Signature {
     /// Signature V value.
@@ -129,8 +264,11 @@ impl Display for Signature {
     }
 }
 
-mod prefixed_hex {
-    use serde::ser::Serializer;
+pub(crate) mod prefixed_hex {
+    use serde::{
+        de::{Deserialize, Deserializer, Error},
+        ser::Serializer,
+    };
 
     pub fn serialize<T, S>(value: T, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -139,10 +277,22 @@ mod prefixed_hex {
     {
         serializer.serialize_str(&format!("0x{}", hex::encode(value)))
     }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        let value = value.strip_prefix("0x").unwrap_or(&value);
+        hex::decode(value).map_err(D::Error::custom)
+    }
 }
 
 mod bigint {
-    use serde::ser::{Serialize, Serializer};
+    use serde::{
+        de::{self, Deserialize, Deserializer},
+        ser::{Serialize, Serializer},
+    };
     use serde_json::Number;
     use std::cmp;
 
@@ -155,6 +305,48 @@ mod bigint {
         Serialize::serialize(&number, serializer)
     }
 
+    /// Deserializes a big-endian 256-bit integer from a JSON number (the
+    /// inverse of `serialize`), as used for the `r`/`s` signature values.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<[u8; 32], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let number = Number::deserialize(deserializer)?;
+        dtoh(&number.to_string()).map_err(de::Error::custom)
+    }
+
+    /// Parses a decimal string into a big-endian 256-bit integer.
+    fn dtoh(digits: &str) -> Result<[u8; 32], String> {
+        let mut bytes = vec![0u8];
+        for c in digits.chars() {
+            let digit = c
+                .to_digit(10)
+                .ok_or_else(|| format!("invalid decimal digit in '{}'", digits))?
+                as u16;
+
+            let mut carry = digit;
+            for byte in bytes.iter_mut() {
+                let value = u16::from(*byte) * 10 + carry;
+                *byte = value as u8;
+                carry = value >> 8;
+            }
+            while carry > 0 {
+                bytes.push(carry as u8);
+                carry >>= 8;
+            }
+        }
+
+        if bytes.len() > 32 {
+            return Err(format!("'{}' does not fit in a 256-bit integer", digits));
+        }
+        bytes.resize(32, 0);
+        bytes.reverse();
+
+        let mut result = [0; 32];
+        result.copy_from_slice(&bytes);
+        Ok(result)
+    }
+
     fn htod(data: &[u8]) -> String {
         let mut digits = vec![0];
 
@@ -209,5 +401,24 @@ mod bigint {
                 "29896827243324578634929412110615083579682215894261272964879659419979876162286",
             );
         }
+
+        #[test]
+        fn decimal_to_hex() {
+            let mut expected = [0; 32];
+            expected[30..].copy_from_slice(&hex!("1337"));
+            assert_eq!(dtoh(&0x1337.to_string()).unwrap(), expected);
+            let decimal =
+                "29896827243324578634929412110615083579682215894261272964879659419979876162286";
+            assert_eq!(
+                dtoh(decimal).unwrap(),
+                hex!("4219012af844056582bc69399c238dd2089815a4164d46b9c43ce315852c5aee"),
+            );
+        }
+
+        #[test]
+        fn decimal_to_hex_rejects_overflow() {
+            let too_big = "1".repeat(100);
+            assert!(dtoh(&too_big).is_err());
+        }
     }
 }
@@ -1,15 +1,52 @@
-use anyhow::{ensure, Result};
+use anyhow::{anyhow, ensure, Result};
 use curl::easy::{Easy, List};
 use serde::{de::DeserializeOwned, Serialize};
-use std::{io::Read, str};
+use std::{error::Error, io::Read, str};
 
 /// Perform an HTTP GET request and return some JSON.
 pub fn get_json<T>(url: impl AsRef<str>) -> Result<T>
 where
     T: DeserializeOwned,
 {
+    let (code, buffer) = get(url.as_ref())?;
+    check_response_code(code, &buffer)?;
+    Ok(serde_json::from_slice(&buffer)?)
+}
+
+/// Perform an HTTP POST request and return some JSON.
+pub fn post_json<T, U>(url: impl AsRef<str>, body: &T) -> Result<U>
+where
+    T: Serialize,
+    U: DeserializeOwned,
+{
+    let (code, buffer) = post(url.as_ref(), &serde_json::to_vec(body)?)?;
+    check_response_code(code, &buffer)?;
+    Ok(serde_json::from_slice(&buffer)?)
+}
+
+/// Like `post_json`, but on a non-2xx response, parses the response body as
+/// `E` instead of returning a generic error, so callers can match on the
+/// failure programmatically.
+pub fn post_json_or<T, U, E>(url: impl AsRef<str>, body: &T) -> Result<U>
+where
+    T: Serialize,
+    U: DeserializeOwned,
+    E: DeserializeOwned + Error + Send + Sync + 'static,
+{
+    let (code, buffer) = post(url.as_ref(), &serde_json::to_vec(body)?)?;
+    if (200..300).contains(&code) {
+        Ok(serde_json::from_slice(&buffer)?)
+    } else {
+        Err(match serde_json::from_slice::<E>(&buffer) {
+            Ok(err) => err.into(),
+            Err(_) => anyhow!("HTTP {}: {}", code, String::from_utf8_lossy(&buffer)),
+        })
+    }
+}
+
+fn get(url: &str) -> Result<(u32, Vec<u8>)> {
     let mut easy = Easy::new();
-    easy.url(url.as_ref())?;
+    easy.url(url)?;
 
     let mut buffer = Vec::new();
     {
@@ -21,23 +58,14 @@ where
         transfer.perform()?;
     }
 
-    check_response_code(&mut easy, &buffer)?;
-
-    let result = serde_json::from_slice(&buffer)?;
-    Ok(result)
+    Ok((easy.response_code()?, buffer))
 }
 
-/// Perform an HTTP POST request and return some JSON.
-pub fn post_json<T, U>(url: impl AsRef<str>, body: &T) -> Result<U>
-where
-    T: Serialize,
-    U: DeserializeOwned,
-{
+fn post(url: &str, body: &[u8]) -> Result<(u32, Vec<u8>)> {
     let mut easy = Easy::new();
-    easy.url(url.as_ref())?;
+    easy.url(url)?;
     easy.post(true)?;
 
-    let body = serde_json::to_vec(body)?;
     easy.post_field_size(body.len() as _)?;
     easy.http_headers({
         let mut list = List::new();
@@ -45,7 +73,7 @@ where
         list
     })?;
 
-    let mut body = &body[..];
+    let mut body = body;
     let mut buffer = Vec::new();
     {
         let mut transfer = easy.transfer();
@@ -57,19 +85,15 @@ where
         transfer.perform()?;
     }
 
-    check_response_code(&mut easy, &buffer)?;
-
-    let result = serde_json::from_slice(&buffer)?;
-    Ok(result)
+    Ok((easy.response_code()?, buffer))
 }
 
-fn check_response_code(easy: &mut Easy, response: &[u8]) -> Result<()> {
-    let code = easy.response_code()?;
+fn check_response_code(code: u32, response: &[u8]) -> Result<()> {
     ensure!(
         code >= 200 && code < 400,
         "HTTP {}: {}",
         code,
-        str::from_utf8(&response)?,
+        str::from_utf8(response)?,
     );
 
     Ok(())
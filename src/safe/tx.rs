@@ -1,9 +1,84 @@
 use crate::{
     address::Address,
-    hash,
-    safe::data::{Operation, SignedSafeTransaction},
+    hash::typed_data::{self, Field, StructType, Value},
+    safe::data::{Operation, Signature, SignedSafeTransaction},
     secret::PrivateKey,
 };
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// The EIP-712 domain for Safes whose version doesn't bind the chain id
+/// (before 1.2.0).
+const DOMAIN_WITHOUT_CHAIN_ID: StructType = StructType {
+    name: "EIP712Domain",
+    fields: &[Field {
+        name: "verifyingContract",
+        ty: "address",
+    }],
+};
+
+/// The EIP-712 domain for Safes whose version binds the chain id (1.2.0 and
+/// later), to prevent cross-chain signature replay.
+const DOMAIN_WITH_CHAIN_ID: StructType = StructType {
+    name: "EIP712Domain",
+    fields: &[
+        Field {
+            name: "chainId",
+            ty: "uint256",
+        },
+        Field {
+            name: "verifyingContract",
+            ty: "address",
+        },
+    ],
+};
+
+/// The EIP-712 struct type for a Safe transaction.
+const SAFE_TX: StructType = StructType {
+    name: "SafeTx",
+    fields: &[
+        Field {
+            name: "to",
+            ty: "address",
+        },
+        Field {
+            name: "value",
+            ty: "uint256",
+        },
+        Field {
+            name: "data",
+            ty: "bytes",
+        },
+        Field {
+            name: "operation",
+            ty: "uint8",
+        },
+        Field {
+            name: "safeTxGas",
+            ty: "uint256",
+        },
+        Field {
+            name: "baseGas",
+            ty: "uint256",
+        },
+        Field {
+            name: "gasPrice",
+            ty: "uint256",
+        },
+        Field {
+            name: "gasToken",
+            ty: "address",
+        },
+        Field {
+            name: "refundReceiver",
+            ty: "address",
+        },
+        Field {
+            name: "nonce",
+            ty: "uint256",
+        },
+    ],
+};
 
 /// A Safe transaction.
 pub struct SafeTransaction {
@@ -31,53 +106,63 @@ pub struct SafeTransaction {
 
 impl SafeTransaction {
     /// Computes the transaction EIP-712 signing hash for this transaction.
-    pub fn hash(&self, safe: Address) -> [u8; 32] {
-        let mut buffer = [0u8; 66];
-        buffer[0..2].copy_from_slice(b"\x19\x01");
-        buffer[2..34].copy_from_slice(&{
-            let mut buffer = [0u8; 64];
-            buffer[0..32].copy_from_slice(&hash::keccak256(
-                "EIP712Domain(\
-                    address verifyingContract\
-                )",
-            ));
-            buffer[44..64].copy_from_slice(&*safe);
-            hash::keccak256(buffer)
-        });
-        buffer[34..66].copy_from_slice(&{
-            let mut buffer = [0u8; 352];
-            buffer[0..32].copy_from_slice(&hash::keccak256(
-                "SafeTx(\
-                    address to,\
-                    uint256 value,\
-                    bytes data,\
-                    uint8 operation,\
-                    uint256 safeTxGas,\
-                    uint256 baseGas,\
-                    uint256 gasPrice,\
-                    address gasToken,\
-                    address refundReceiver,\
-                    uint256 nonce\
-                )",
-            ));
-            buffer[44..64].copy_from_slice(&*self.to);
-            buffer[80..96].copy_from_slice(&self.value.to_be_bytes());
-            buffer[96..128].copy_from_slice(&hash::keccak256(&self.data));
-            buffer[159] = self.operation as u8;
-            buffer[176..192].copy_from_slice(&self.safe_tx_gas.to_be_bytes());
-            buffer[208..224].copy_from_slice(&self.base_gas.to_be_bytes());
-            buffer[240..256].copy_from_slice(&self.gas_price.to_be_bytes());
-            buffer[268..288].copy_from_slice(&*self.gas_token.unwrap_or_default());
-            buffer[300..320].copy_from_slice(&*self.refund_receiver.unwrap_or_default());
-            buffer[344..352].copy_from_slice(&self.nonce.to_be_bytes());
-            hash::keccak256(buffer)
-        });
-
-        hash::keccak256(buffer)
+    ///
+    /// The `version` is the Safe contract version being migrated (as
+    /// returned by `SafeInfo::version`) and determines the shape of the
+    /// EIP-712 domain: Safes before 1.2.0 use a domain keyed only on the
+    /// verifying contract, while 1.2.0 and later also bind the domain to
+    /// the `chain_id` to prevent cross-chain signature replay.
+    pub fn hash(&self, safe: Address, chain_id: u64, version: &str) -> [u8; 32] {
+        let domain_type = if binds_chain_id(version) {
+            DOMAIN_WITH_CHAIN_ID
+        } else {
+            DOMAIN_WITHOUT_CHAIN_ID
+        };
+        let domain = if binds_chain_id(version) {
+            vec![
+                ("chainId", Value::Uint(chain_id as u128)),
+                ("verifyingContract", Value::Address(*safe)),
+            ]
+        } else {
+            vec![("verifyingContract", Value::Address(*safe))]
+        };
+
+        let message = vec![
+            ("to", Value::Address(*self.to)),
+            ("value", Value::Uint(self.value)),
+            ("data", Value::Bytes(self.data.clone())),
+            ("operation", Value::Uint8(self.operation as u8)),
+            ("safeTxGas", Value::Uint(self.safe_tx_gas)),
+            ("baseGas", Value::Uint(self.base_gas)),
+            ("gasPrice", Value::Uint(self.gas_price)),
+            (
+                "gasToken",
+                Value::Address(*self.gas_token.unwrap_or_default()),
+            ),
+            (
+                "refundReceiver",
+                Value::Address(*self.refund_receiver.unwrap_or_default()),
+            ),
+            ("nonce", Value::Uint(self.nonce as u128)),
+        ];
+
+        typed_data::hash(
+            &[domain_type, SAFE_TX],
+            "EIP712Domain",
+            &domain,
+            "SafeTx",
+            &message,
+        )
     }
 
     /// Signs a transaction with the specified private key.
-    pub fn sign(&self, safe: Address, key: &PrivateKey) -> SignedSafeTransaction {
+    pub fn sign(
+        &self,
+        safe: Address,
+        chain_id: u64,
+        version: &str,
+        key: &PrivateKey,
+    ) -> SignedSafeTransaction {
         SignedSafeTransaction {
             safe,
             to: self.to,
@@ -90,11 +175,143 @@ impl SafeTransaction {
             gas_price: self.gas_price,
             refund_receiver: self.refund_receiver,
             nonce: self.nonce,
-            signatures: vec![key.sign(self.hash(safe))],
+            signatures: vec![key.sign(self.hash(safe, chain_id, version))],
+        }
+    }
+
+    /// Bundles this transaction into a self-contained, JSON-serializable
+    /// dump that can be carried to an air-gapped machine for signing: the
+    /// `safe_tx_hash` is precomputed here, so an offline signer never needs
+    /// network access or the Safe's on-chain configuration to know what
+    /// they're signing.
+    pub fn dump(&self, safe: Address, chain_id: u64, version: &str) -> UnsignedSafeTransaction {
+        UnsignedSafeTransaction {
+            safe,
+            chain_id,
+            to: self.to,
+            value: self.value,
+            data: self.data.clone(),
+            operation: self.operation,
+            safe_tx_gas: self.safe_tx_gas,
+            base_gas: self.base_gas,
+            gas_price: self.gas_price,
+            gas_token: self.gas_token,
+            refund_receiver: self.refund_receiver,
+            nonce: self.nonce,
+            safe_tx_hash: format!("0x{}", hex::encode(self.hash(safe, chain_id, version))),
+        }
+    }
+}
+
+/// A `SafeTransaction` dump suitable for offline signing. It can be written
+/// to and read back from JSON, so a signer can prepare a transaction,
+/// distribute the dump, and have owners sign it independently before
+/// anyone broadcasts it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedSafeTransaction {
+    /// The Safe this transaction is for.
+    pub safe: Address,
+    /// The chain id the transaction hash was computed for.
+    pub chain_id: u64,
+    /// The transaction to address.
+    pub to: Address,
+    /// The amount of ETH being transaferred.
+    pub value: u128,
+    /// The transfer call data.
+    #[serde(with = "crate::safe::data::prefixed_hex")]
+    pub data: Vec<u8>,
+    /// The operation kind.
+    pub operation: Operation,
+    /// The safe transaction gas.
+    pub safe_tx_gas: u128,
+    /// The base gas required to execute the transaction.
+    pub base_gas: u128,
+    /// The gas price.
+    pub gas_price: u128,
+    /// The gas token to pay the transaction in.
+    pub gas_token: Option<Address>,
+    /// The receiver of the gas refund.
+    pub refund_receiver: Option<Address>,
+    /// The transaction nonce to use.
+    pub nonce: u64,
+    /// The precomputed EIP-712 signing hash, as a `0x`-prefixed hex string.
+    pub safe_tx_hash: String,
+}
+
+impl UnsignedSafeTransaction {
+    /// Parses `safe_tx_hash` back into raw bytes.
+    fn hash(&self) -> Result<[u8; 32]> {
+        let hash = self.safe_tx_hash.strip_prefix("0x").unwrap_or(&self.safe_tx_hash);
+        hex::decode(hash)?
+            .try_into()
+            .map_err(|_| anyhow!("invalid safe_tx_hash"))
+    }
+
+    /// Combines this dump with the signatures collected for it, ready to be
+    /// submitted to the relay.
+    fn into_signed_transaction(self, signatures: Vec<Signature>) -> SignedSafeTransaction {
+        SignedSafeTransaction {
+            safe: self.safe,
+            to: self.to,
+            value: self.value,
+            data: self.data,
+            operation: self.operation,
+            gas_token: self.gas_token,
+            safe_tx_gas: self.safe_tx_gas,
+            data_gas: self.base_gas,
+            gas_price: self.gas_price,
+            refund_receiver: self.refund_receiver,
+            nonce: self.nonce,
+            signatures,
         }
     }
 }
 
+/// Signs an unsigned dump's precomputed `safe_tx_hash` with the given
+/// private key, without making any network calls. Used to collect owner
+/// signatures on an air-gapped machine before the transaction is relayed.
+pub fn sign_offline(dump: &UnsignedSafeTransaction, key: &PrivateKey) -> Result<Signature> {
+    Ok(key.sign(dump.hash()?))
+}
+
+/// An `UnsignedSafeTransaction` dump together with the signatures collected
+/// for it offline, ready to be relayed for execution.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SignedSafeTransactionDump {
+    #[serde(flatten)]
+    pub transaction: UnsignedSafeTransaction,
+    pub signatures: Vec<Signature>,
+}
+
+impl SignedSafeTransactionDump {
+    /// Converts the dump into the `SignedSafeTransaction` payload the relay
+    /// service expects.
+    pub fn into_signed_transaction(self) -> SignedSafeTransaction {
+        self.transaction.into_signed_transaction(self.signatures)
+    }
+}
+
+/// Returns whether the given Safe contract version's EIP-712 domain is
+/// bound to the chain id, which is the case for Safe 1.2.0 and later.
+fn binds_chain_id(version: &str) -> bool {
+    parse_version(version).map_or(false, |version| version >= (1, 2, 0))
+}
+
+/// Returns whether the given Safe contract version is supported by this
+/// tool, i.e. it is at least version 1.1.1.
+pub fn is_supported_version(version: &str) -> bool {
+    parse_version(version).map_or(false, |version| version >= (1, 1, 1))
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts.next()?.parse().ok()?;
+    Some((major, minor, patch))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,8 +332,99 @@ mod tests {
                 refund_receiver: Some(Address([8; 20])),
                 nonce: 9,
             }
-            .hash(Address(hex!("0b54478f3a29BfAD2b67a0d7Dbe23e8f61B1EbC6"))),
+            .hash(
+                Address(hex!("0b54478f3a29BfAD2b67a0d7Dbe23e8f61B1EbC6")),
+                1,
+                "1.1.1",
+            ),
             hex!("59485d05fff460e1687ea64c018781e440cbd8cb6a14c82d1ee2c7756fe4f7cb"),
         );
     }
+
+    #[test]
+    fn binds_chain_id_from_1_2_0_onwards() {
+        assert!(!binds_chain_id("1.1.1"));
+        assert!(binds_chain_id("1.2.0"));
+        assert!(binds_chain_id("1.3.0"));
+    }
+
+    #[test]
+    fn supports_known_versions() {
+        assert!(!is_supported_version("1.0.0"));
+        assert!(is_supported_version("1.1.1"));
+        assert!(is_supported_version("1.3.0"));
+        assert!(!is_supported_version("garbage"));
+    }
+
+    #[test]
+    fn offline_signing_round_trip() {
+        let seed_phrase =
+            "myth like bonus scare over problem client lizard pioneer submit female collect";
+        let key = PrivateKey::from_mnemonic(seed_phrase).unwrap();
+        let safe = Address(hex!("0b54478f3a29BfAD2b67a0d7Dbe23e8f61B1EbC6"));
+
+        let tx = SafeTransaction {
+            to: Address([1; 20]),
+            value: 2,
+            data: vec![3],
+            operation: Operation::Call,
+            safe_tx_gas: 4,
+            base_gas: 5,
+            gas_price: 6,
+            gas_token: Some(Address([7; 20])),
+            refund_receiver: Some(Address([8; 20])),
+            nonce: 9,
+        };
+        let dump = tx.dump(safe, 1, "1.1.1");
+
+        // The dump's precomputed hash survives a JSON round trip unchanged.
+        let json = serde_json::to_string(&dump).unwrap();
+        let roundtripped: UnsignedSafeTransaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtripped.safe_tx_hash, dump.safe_tx_hash);
+
+        let signature = sign_offline(&roundtripped, &key).unwrap();
+        assert_eq!(
+            signature.recover(tx.hash(safe, 1, "1.1.1")).unwrap(),
+            key.address(),
+        );
+    }
+
+    #[test]
+    fn signed_dump_round_trip() {
+        let seed_phrase =
+            "myth like bonus scare over problem client lizard pioneer submit female collect";
+        let key = PrivateKey::from_mnemonic(seed_phrase).unwrap();
+        let safe = Address(hex!("0b54478f3a29BfAD2b67a0d7Dbe23e8f61B1EbC6"));
+
+        let tx = SafeTransaction {
+            to: Address([1; 20]),
+            value: 2,
+            data: vec![3],
+            operation: Operation::Call,
+            safe_tx_gas: 4,
+            base_gas: 5,
+            gas_price: 6,
+            gas_token: Some(Address([7; 20])),
+            refund_receiver: Some(Address([8; 20])),
+            nonce: 9,
+        };
+        let dump = tx.dump(safe, 1, "1.1.1");
+        let signature = sign_offline(&dump, &key).unwrap();
+        let signed_dump = SignedSafeTransactionDump {
+            transaction: dump,
+            signatures: vec![signature],
+        };
+
+        // A dump together with its offline-collected signature also
+        // survives a JSON round trip.
+        let json = serde_json::to_string(&signed_dump).unwrap();
+        let roundtripped: SignedSafeTransactionDump = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            roundtripped.signatures[0].recover(tx.hash(safe, 1, "1.1.1")).unwrap(),
+            key.address(),
+        );
+
+        let signed_tx = roundtripped.into_signed_transaction();
+        assert_eq!(signed_tx.signatures[0], signed_dump.signatures[0]);
+    }
 }
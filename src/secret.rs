@@ -1,8 +1,13 @@
-use crate::{address::Address, hash, safe::data::Signature};
-use anyhow::{anyhow, Result};
+use crate::{
+    address::Address,
+    hash,
+    safe::{data::Signature, tx::SafeTransaction},
+};
+use anyhow::{anyhow, bail, Result};
 use bip39::{Language, Mnemonic, Seed};
 use secp256k1::{
     key::{PublicKey, SecretKey, ONE_KEY},
+    recovery::{RecoverableSignature, RecoveryId},
     Message, Secp256k1,
 };
 use std::fmt::{self, Debug, Formatter};
@@ -65,6 +70,106 @@ impl PrivateKey {
     }
 }
 
+/// A signer backed by a raw secp256k1 private key, for an owner who holds a
+/// private key directly rather than deriving one from a mnemonic seed
+/// phrase (see `PrivateKey`).
+pub struct Signer(SecretKey);
+
+impl Signer {
+    /// Creates a signer from a raw 32-byte secp256k1 private key.
+    pub fn new(secret_key: [u8; 32]) -> Result<Self> {
+        Ok(Signer(SecretKey::from_slice(&secret_key)?))
+    }
+
+    /// Returns the public address for the signer.
+    pub fn address(&self) -> Address {
+        let secp = Secp256k1::signing_only();
+        let public_key = PublicKey::from_secret_key(&secp, &self.0).serialize_uncompressed();
+
+        debug_assert_eq!(public_key[0], 0x04);
+        let hash = hash::keccak256(&public_key[1..]);
+        Address::from_slice(&hash[12..])
+    }
+
+    /// Signs a Safe transaction's EIP-712 digest, producing a signature
+    /// tagged with this signer's address so multiple owners' signatures can
+    /// later be sorted into the order the Safe contract expects.
+    ///
+    /// The digest is signed directly (rather than `personal_sign`-prefixed),
+    /// so the Safe contract's `+4` "eth_sign" `v` convention does not apply
+    /// here; `v` is the usual `27`/`28`.
+    pub fn sign_safe_transaction(
+        &self,
+        tx: &SafeTransaction,
+        safe: Address,
+        chain_id: u64,
+        version: &str,
+    ) -> Result<OwnerSignature> {
+        let digest = tx.hash(safe, chain_id, version);
+        let message = Message::from_slice(&digest).expect("invalid message");
+
+        let (recovery_id, raw_signature) = Secp256k1::signing_only()
+            .sign_recoverable(&message, &self.0)
+            .serialize_compact();
+        debug_assert!(matches!(recovery_id.to_i32(), 0 | 1));
+
+        let mut signature = Signature::default();
+        signature.v = 27 + (recovery_id.to_i32() as u8);
+        signature.r.copy_from_slice(&raw_signature[..32]);
+        signature.s.copy_from_slice(&raw_signature[32..]);
+
+        Ok(OwnerSignature {
+            owner: self.address(),
+            signature,
+        })
+    }
+}
+
+/// A signature from a single Safe owner, tagged with the signer's address
+/// so multiple signatures can be ordered the way the Safe contract expects.
+#[derive(Clone, Debug)]
+pub struct OwnerSignature {
+    pub owner: Address,
+    pub signature: Signature,
+}
+
+/// Sorts owner signatures by signer address, ascending, and returns the
+/// plain signatures in the order the Safe contract requires them submitted
+/// in, ready to be assigned to `SignedSafeTransaction::signatures`.
+pub fn sort_owner_signatures(mut signatures: Vec<OwnerSignature>) -> Vec<Signature> {
+    signatures.sort_by(|a, b| (*a.owner).cmp(&*b.owner));
+    signatures.into_iter().map(|s| s.signature).collect()
+}
+
+impl Signature {
+    /// Recovers the address that produced this signature over the
+    /// specified message hash.
+    pub fn recover(&self, hash: [u8; 32]) -> Result<Address> {
+        let recovery_id = match self.v {
+            27 | 28 => self.v - 27,
+            v if v >= 35 => (v - 35) % 2,
+            v => bail!("invalid signature recovery id {}", v),
+        };
+
+        let mut compact = [0u8; 64];
+        compact[..32].copy_from_slice(&self.r);
+        compact[32..].copy_from_slice(&self.s);
+        let recoverable_signature = RecoverableSignature::from_compact(
+            &compact,
+            RecoveryId::from_i32(recovery_id as i32)?,
+        )?;
+
+        let message = Message::from_slice(&hash)?;
+        let public_key = Secp256k1::verification_only()
+            .recover(&message, &recoverable_signature)?
+            .serialize_uncompressed();
+
+        debug_assert_eq!(public_key[0], 0x04);
+        let hash = hash::keccak256(&public_key[1..]);
+        Ok(Address::from_slice(&hash[12..]))
+    }
+}
+
 impl Debug for PrivateKey {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         f.debug_tuple("PrivateKey").field(&self.address()).finish()
@@ -119,4 +224,66 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn recovers_signer_address() {
+        let key = PrivateKey::from_mnemonic(GANACHE_DETERMINISTIC_MNEMONIC).unwrap();
+        let message = hash::keccak256(b"\x19Ethereum Signed Message:\n12Hello World!");
+        let signature = key.sign(message);
+
+        assert_eq!(signature.recover(message).unwrap(), key.address());
+    }
+
+    #[test]
+    fn signer_signs_safe_transaction() {
+        use crate::safe::data::Operation;
+
+        let signer = Signer::new([0x11; 32]).unwrap();
+        let safe = Address(hex!("0b54478f3a29BfAD2b67a0d7Dbe23e8f61B1EbC6"));
+        let tx = SafeTransaction {
+            to: Address([1; 20]),
+            value: 2,
+            data: vec![3],
+            operation: Operation::Call,
+            safe_tx_gas: 4,
+            base_gas: 5,
+            gas_price: 6,
+            gas_token: Some(Address([7; 20])),
+            refund_receiver: Some(Address([8; 20])),
+            nonce: 9,
+        };
+
+        let owner_signature = signer.sign_safe_transaction(&tx, safe, 1, "1.1.1").unwrap();
+        assert_eq!(owner_signature.owner, signer.address());
+        assert_eq!(
+            owner_signature
+                .signature
+                .recover(tx.hash(safe, 1, "1.1.1"))
+                .unwrap(),
+            signer.address(),
+        );
+    }
+
+    #[test]
+    fn sorts_owner_signatures_by_address() {
+        let low = OwnerSignature {
+            owner: Address([1; 20]),
+            signature: Signature {
+                v: 27,
+                ..Signature::default()
+            },
+        };
+        let high = OwnerSignature {
+            owner: Address([2; 20]),
+            signature: Signature {
+                v: 28,
+                ..Signature::default()
+            },
+        };
+
+        assert_eq!(
+            sort_owner_signatures(vec![high.clone(), low.clone()]),
+            vec![low.signature, high.signature],
+        );
+    }
 }